@@ -0,0 +1,149 @@
+//! Derive macro companion for `fdt_parse`.
+//!
+//! `#[derive(FromFdtNode)]` generates a [`fdt_parse::FromFdtNode`] impl that
+//! pulls one property per struct field, decoding each through
+//! `fdt_parse::FromFdtProperty`. Fields typed `Option<T>` are optional; every
+//! other field is required and yields `FdtError::NotFound` when absent. The
+//! property name defaults to the field name and can be overridden with
+//! `#[fdt(rename = "...")]`. Fields marked `#[fdt(reg)]` or `#[fdt(ranges)]`
+//! are instead decoded through `FdtNode::reg` / `FdtNode::ranges`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, GenericArgument, PathArguments, Type};
+
+#[proc_macro_derive(FromFdtNode, attributes(fdt))]
+pub fn derive_from_fdt_node(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => &named.named,
+            _ => {
+                return syn::Error::new_spanned(name, "FromFdtNode requires named fields")
+                    .to_compile_error()
+                    .into()
+            }
+        },
+        _ => {
+            return syn::Error::new_spanned(name, "FromFdtNode can only derive for structs")
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    // Reuse the struct's own lifetime for the borrowed property data, or
+    // introduce one when the struct owns nothing borrowed.
+    let existing_lt = input.generics.lifetimes().next().map(|lp| lp.lifetime.clone());
+    let lt = existing_lt
+        .clone()
+        .unwrap_or_else(|| syn::Lifetime::new("'fdt", proc_macro2::Span::call_site()));
+
+    // `ty_generics`/`where_clause` follow the struct as written, but when we had
+    // to invent `'fdt` it must also be declared on the `impl`, so build the impl
+    // generics from a copy that carries it.
+    let mut impl_generics_src = input.generics.clone();
+    if existing_lt.is_none() {
+        let param: syn::GenericParam = parse_quote!(#lt);
+        impl_generics_src.params.insert(0, param);
+    }
+    let (impl_generics, _, _) = impl_generics_src.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut assigns = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let attr = fdt_attr(field);
+        let optional = option_inner(&field.ty).is_some();
+
+        let value = match attr.kind {
+            // `reg`/`ranges` inherit their cell counts from the enclosing node,
+            // so they are decoded through the node rather than a raw property.
+            FieldKind::Reg if optional => quote! { node.reg() },
+            FieldKind::Reg => quote! { node.reg().ok_or(::fdt_parse::FdtError::NotFound)? },
+            FieldKind::Ranges if optional => quote! { node.ranges() },
+            FieldKind::Ranges => quote! { node.ranges().ok_or(::fdt_parse::FdtError::NotFound)? },
+            FieldKind::Property => {
+                let prop_name = attr.rename.unwrap_or_else(|| ident.to_string());
+                if let Some(inner) = option_inner(&field.ty) {
+                    quote! {
+                        match node.property_by_name(#prop_name) {
+                            Some(prop) => Some(<#inner as ::fdt_parse::FromFdtProperty>::from_fdt_property(prop.value)?),
+                            None => None,
+                        }
+                    }
+                } else {
+                    let ty = &field.ty;
+                    quote! {
+                        <#ty as ::fdt_parse::FromFdtProperty>::from_fdt_property(
+                            node.property_by_name(#prop_name).ok_or(::fdt_parse::FdtError::NotFound)?.value,
+                        )?
+                    }
+                }
+            }
+        };
+        assigns.push(quote! { #ident: #value });
+    }
+
+    let expanded = quote! {
+        impl #impl_generics ::fdt_parse::FromFdtNode<#lt> for #name #ty_generics #where_clause {
+            fn from_fdt_node(node: &::fdt_parse::FdtNode<#lt>) -> ::core::result::Result<Self, ::fdt_parse::FdtError> {
+                Ok(Self {
+                    #(#assigns),*
+                })
+            }
+        }
+    };
+    expanded.into()
+}
+
+/// How a field is sourced from the node.
+enum FieldKind {
+    Property,
+    Reg,
+    Ranges,
+}
+
+/// Parsed `#[fdt(...)]` attribute: the field kind and an optional rename.
+struct FdtAttr {
+    kind: FieldKind,
+    rename: Option<String>,
+}
+
+/// Parse `#[fdt(rename = "...")]`, `#[fdt(reg)]` and `#[fdt(ranges)]`.
+fn fdt_attr(field: &syn::Field) -> FdtAttr {
+    let mut parsed = FdtAttr { kind: FieldKind::Property, rename: None };
+    for attr in &field.attrs {
+        if !attr.path().is_ident("fdt") {
+            continue;
+        }
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let value = meta.value()?;
+                let lit: syn::LitStr = value.parse()?;
+                parsed.rename = Some(lit.value());
+            } else if meta.path.is_ident("reg") {
+                parsed.kind = FieldKind::Reg;
+            } else if meta.path.is_ident("ranges") {
+                parsed.kind = FieldKind::Ranges;
+            }
+            Ok(())
+        });
+    }
+    parsed
+}
+
+/// If `ty` is `Option<T>`, return `T`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let seg = path.path.segments.last()?;
+    if seg.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &seg.arguments else { return None };
+    args.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(t) => Some(t),
+        _ => None,
+    })
+}
@@ -2,8 +2,14 @@
 //! A crate to parse a Flattened Device Tree (FDT)
 //! into a structure intended for immediate consupmtion
 //! by the operating system.
+
+// Allows the `#[derive(FromFdtNode)]` macro's `::fdt_parse::` paths to resolve
+// from within this crate (e.g. in its own tests).
+extern crate self as fdt_parse;
+
+pub use fdt_derive::FromFdtNode;
+
 use core::ffi::CStr;
-use core::num;
 use core::slice;
 use core::mem;
 
@@ -33,7 +39,7 @@ pub struct FdtHeader {
 #[derive(Debug)]
 pub struct Fdt<'a> {
     pub header: FdtHeader,
-    reserved_memory: &'a[FdtReserveEntry],
+    reserved_memory: &'a [u8],
     pub dt_struct: &'a [u8],
     pub dt_strings: &'a [u8]
 }
@@ -56,26 +62,204 @@ pub enum FdtError {
     InvalidMagic,
     InvalidPointer,
     NotFound,
+    BufferTooSmall,
+    Truncated,
+    OffsetOutOfBounds,
+    UnsupportedVersion,
+}
+
+/// Builder that serializes a flattened device tree into a caller-provided buffer.
+///
+/// The writer is the inverse of [`Fdt`]: a consumer describes a tree with
+/// [`begin_node`](FdtWriter::begin_node) / [`property`](FdtWriter::property) /
+/// [`end_node`](FdtWriter::end_node) calls and [`finish`](FdtWriter::finish)
+/// backfills the [`FdtHeader`] once every block size is known. It allocates
+/// nothing: the structure block is written in place from `off_dt_struct` onward
+/// and the de-duplicated strings block is kept packed immediately after it,
+/// sliding up as the structure block grows.
+pub struct FdtWriter<'a> {
+    buf: &'a mut [u8],
+    struct_len: usize,
+    strings_len: usize,
+}
+
+impl<'a> FdtWriter<'a> {
+    /// Offset of the structure block: header (40 bytes) plus a single
+    /// terminating memory-reservation entry (16 bytes).
+    const OFF_DT_STRUCT: usize = mem::size_of::<FdtHeader>() + mem::size_of::<FdtReserveEntry>();
+
+    pub fn new(buf: &'a mut [u8]) -> FdtWriter<'a> {
+        FdtWriter { buf, struct_len: 0, strings_len: 0 }
+    }
+
+    /// Absolute offset at which the strings block currently starts.
+    fn strings_base(&self) -> usize {
+        Self::OFF_DT_STRUCT + self.struct_len
+    }
+
+    /// Insert `bytes` at the end of the structure block, sliding the strings
+    /// block up to make room.
+    fn write_struct(&mut self, bytes: &[u8]) -> Result<(), FdtError> {
+        let sb = self.strings_base();
+        if sb + bytes.len() + self.strings_len > self.buf.len() {
+            return Err(FdtError::BufferTooSmall);
+        }
+        self.buf.copy_within(sb..sb + self.strings_len, sb + bytes.len());
+        self.buf[sb..sb + bytes.len()].copy_from_slice(bytes);
+        self.struct_len += bytes.len();
+        Ok(())
+    }
+
+    fn write_token(&mut self, token: u32) -> Result<(), FdtError> {
+        self.write_struct(&token.to_be_bytes())
+    }
+
+    /// Write `value` as a structure-block payload padded to a 4-byte boundary.
+    fn write_padded(&mut self, value: &[u8]) -> Result<(), FdtError> {
+        self.write_struct(value)?;
+        let pad = align4(value.len()) - value.len();
+        if pad != 0 {
+            self.write_struct(&[0u8; 4][..pad])?;
+        }
+        Ok(())
+    }
+
+    /// Intern a property name, returning its `nameoff` in the strings block.
+    fn intern(&mut self, name: &str) -> Result<u32, FdtError> {
+        let base = self.strings_base();
+        let mut off = 0;
+        while off < self.strings_len {
+            let rest = &self.buf[base + off..base + self.strings_len];
+            let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+            if &rest[..end] == name.as_bytes() {
+                return Ok(off as u32);
+            }
+            off += end + 1;
+        }
+        let off = self.strings_len;
+        if base + off + name.len() + 1 > self.buf.len() {
+            return Err(FdtError::BufferTooSmall);
+        }
+        self.buf[base + off..base + off + name.len()].copy_from_slice(name.as_bytes());
+        self.buf[base + off + name.len()] = 0;
+        self.strings_len += name.len() + 1;
+        Ok(off as u32)
+    }
+
+    pub fn begin_node(&mut self, name: &str) -> Result<(), FdtError> {
+        self.write_token(FDT_BEGIN_NODE)?;
+        self.write_struct(name.as_bytes())?;
+        self.write_struct(&[0u8])?;
+        let pad = align4(name.len() + 1) - (name.len() + 1);
+        if pad != 0 {
+            self.write_struct(&[0u8; 4][..pad])?;
+        }
+        Ok(())
+    }
+
+    pub fn end_node(&mut self) -> Result<(), FdtError> {
+        self.write_token(FDT_END_NODE)
+    }
+
+    pub fn property(&mut self, name: &str, value: &[u8]) -> Result<(), FdtError> {
+        let nameoff = self.intern(name)?;
+        self.write_token(FDT_PROP)?;
+        self.write_struct(&(value.len() as u32).to_be_bytes())?;
+        self.write_struct(&nameoff.to_be_bytes())?;
+        self.write_padded(value)
+    }
+
+    pub fn property_u32(&mut self, name: &str, value: u32) -> Result<(), FdtError> {
+        self.property(name, &value.to_be_bytes())
+    }
+
+    pub fn property_u64(&mut self, name: &str, value: u64) -> Result<(), FdtError> {
+        self.property(name, &value.to_be_bytes())
+    }
+
+    pub fn property_string(&mut self, name: &str, value: &str) -> Result<(), FdtError> {
+        let nameoff = self.intern(name)?;
+        self.write_token(FDT_PROP)?;
+        self.write_struct(&((value.len() + 1) as u32).to_be_bytes())?;
+        self.write_struct(&nameoff.to_be_bytes())?;
+        self.write_struct(value.as_bytes())?;
+        self.write_struct(&[0u8])?;
+        let pad = align4(value.len() + 1) - (value.len() + 1);
+        if pad != 0 {
+            self.write_struct(&[0u8; 4][..pad])?;
+        }
+        Ok(())
+    }
+
+    pub fn property_array_u32(&mut self, name: &str, values: &[u32]) -> Result<(), FdtError> {
+        let nameoff = self.intern(name)?;
+        self.write_token(FDT_PROP)?;
+        self.write_struct(&((values.len() * 4) as u32).to_be_bytes())?;
+        self.write_struct(&nameoff.to_be_bytes())?;
+        for v in values {
+            self.write_struct(&v.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Finalize the tree, writing the memory-reservation terminator and the
+    /// [`FdtHeader`], and return the total number of bytes written.
+    pub fn finish(mut self) -> Result<usize, FdtError> {
+        self.write_token(FDT_END)?;
+
+        let off_dt_struct = Self::OFF_DT_STRUCT;
+        let size_dt_struct = self.struct_len;
+        let off_dt_strings = off_dt_struct + size_dt_struct;
+        let size_dt_strings = self.strings_len;
+        let totalsize = off_dt_strings + size_dt_strings;
+        let off_mem_rsvmap = mem::size_of::<FdtHeader>();
+
+        // Terminating (zero) memory reservation entry.
+        for b in &mut self.buf[off_mem_rsvmap..off_mem_rsvmap + mem::size_of::<FdtReserveEntry>()] {
+            *b = 0;
+        }
+
+        let header = [
+            FDT_HDR_MAGIC,
+            totalsize as u32,
+            off_dt_struct as u32,
+            off_dt_strings as u32,
+            off_mem_rsvmap as u32,
+            17,
+            16,
+            0,
+            size_dt_strings as u32,
+            size_dt_struct as u32,
+        ];
+        for (i, word) in header.iter().enumerate() {
+            self.buf[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+        }
+        Ok(totalsize)
+    }
 }
 
 impl Fdt<'_> {
     pub fn new(fdt_addr: *const u8) -> Result<Self, FdtError> {
-        if fdt_addr == 0 as *const u8 { return Err(FdtError::InvalidPointer) }
+        if fdt_addr.is_null() { return Err(FdtError::InvalidPointer) }
         let hdr = Fdt::_parse_header(fdt_addr)?;
         let (mem_reserve, hdr) = Fdt::_parse_mem_reserve(fdt_addr,hdr);
         let (dt_struct, hdr) = Fdt::_parse_dt_struct(fdt_addr, hdr);
         let (dt_strings, hdr) = Fdt::_parse_dt_strings(fdt_addr, hdr);
-        let fdt = Fdt { 
+        let fdt = Fdt {
             header: hdr,
             reserved_memory: mem_reserve,
-            dt_struct: dt_struct,
-            dt_strings: dt_strings
+            dt_struct,
+            dt_strings
         };
-        return Ok(fdt);
+        Ok(fdt)
     }
 
     pub fn get_reserved_memory_regions(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
-        self.reserved_memory.iter().map(|x| (x.address.to_be(), x.size.to_be()))
+        self.reserved_memory.chunks_exact(mem::size_of::<FdtReserveEntry>()).map(|e| {
+            let address = u64::from_be_bytes(e[0..8].try_into().unwrap());
+            let size = u64::from_be_bytes(e[8..16].try_into().unwrap());
+            (address, size)
+        })
     }
 
     pub fn st(&self) -> impl Iterator<Item = &u8> + '_ {
@@ -83,14 +267,112 @@ impl Fdt<'_> {
     }
 
     pub fn get_string(&self, offset: usize) -> Option<&str> {
-        if offset < self.header.size_dt_strings as usize {
-            let sl = &self.dt_strings[offset..self.header.size_dt_strings as usize];
-            let cstr = CStr::from_bytes_until_nul(sl).unwrap();
-            let str = cstr.to_str().unwrap();
-            Some(str)
-        } else {
-            None
+        let sl = self.dt_strings.get(offset..)?;
+        let cstr = CStr::from_bytes_until_nul(sl).ok()?;
+        cstr.to_str().ok()
+    }
+
+    /// Construct an [`Fdt`] from a borrowed byte slice, validating every offset
+    /// in the header before any of it is trusted.
+    ///
+    /// Unlike [`Fdt::new`] this never forges a `'static` lifetime or reads
+    /// through a raw pointer without checking: the buffer must be at least as
+    /// large as an [`FdtHeader`], carry the correct magic, advertise a
+    /// `last_comp_version` this parser understands, and keep its structure,
+    /// strings and memory-reservation blocks inside `totalsize` (and inside the
+    /// slice) without overlapping or overflowing.
+    pub fn from_bytes(data: &[u8]) -> Result<Fdt<'_>, FdtError> {
+        if data.len() < mem::size_of::<FdtHeader>() {
+            return Err(FdtError::Truncated);
+        }
+        let header = Fdt::_read_header(data).ok_or(FdtError::Truncated)?;
+        if header.magic != FDT_HDR_MAGIC {
+            return Err(FdtError::InvalidMagic);
+        }
+        if header.last_comp_version > 17 {
+            return Err(FdtError::UnsupportedVersion);
+        }
+
+        let totalsize = header.totalsize as usize;
+        if totalsize > data.len() {
+            return Err(FdtError::OffsetOutOfBounds);
+        }
+
+        let struct_off = header.off_dt_struct as usize;
+        let strings_off = header.off_dt_strings as usize;
+        let rsvmap_off = header.off_mem_rsvmap as usize;
+        let struct_end = struct_off
+            .checked_add(header.size_dt_struct as usize)
+            .ok_or(FdtError::OffsetOutOfBounds)?;
+        let strings_end = strings_off
+            .checked_add(header.size_dt_strings as usize)
+            .ok_or(FdtError::OffsetOutOfBounds)?;
+
+        if struct_end > totalsize || strings_end > totalsize || rsvmap_off > totalsize {
+            return Err(FdtError::OffsetOutOfBounds);
+        }
+        // The structure and strings blocks must not overlap each other.
+        if !(struct_end <= strings_off || strings_end <= struct_off) {
+            return Err(FdtError::OffsetOutOfBounds);
+        }
+
+        let dt_struct = data.get(struct_off..struct_end).ok_or(FdtError::OffsetOutOfBounds)?;
+        let dt_strings = data.get(strings_off..strings_end).ok_or(FdtError::OffsetOutOfBounds)?;
+        let reserved_memory = Fdt::_scan_mem_reserve(&data[..totalsize], rsvmap_off)?;
+        // The reservation block (including its terminator) must not overlap
+        // the structure or strings block either.
+        let rsvmap_end = rsvmap_off + reserved_memory.len() + mem::size_of::<FdtReserveEntry>();
+        if !(rsvmap_end <= struct_off || struct_end <= rsvmap_off)
+            || !(rsvmap_end <= strings_off || strings_end <= rsvmap_off)
+        {
+            return Err(FdtError::OffsetOutOfBounds);
+        }
+
+        Ok(Fdt { header, reserved_memory, dt_struct, dt_strings })
+    }
+
+    /// Read and byte-swap an [`FdtHeader`] out of `data` without casting through
+    /// a (possibly misaligned) raw pointer.
+    fn _read_header(data: &[u8]) -> Option<FdtHeader> {
+        let word = |i: usize| -> Option<u32> {
+            let b = data.get(i * 4..i * 4 + 4)?;
+            Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        Some(FdtHeader {
+            magic: word(0)?,
+            totalsize: word(1)?,
+            off_dt_struct: word(2)?,
+            off_dt_strings: word(3)?,
+            off_mem_rsvmap: word(4)?,
+            version: word(5)?,
+            last_comp_version: word(6)?,
+            boot_cpuid_phys: word(7)?,
+            size_dt_strings: word(8)?,
+            size_dt_struct: word(9)?,
+        })
+    }
+
+    /// Walk the memory-reservation block from `off` until the terminating zero
+    /// entry, bounds-checking each 16-byte entry against `data`, and return the
+    /// bytes of the non-terminator entries.
+    ///
+    /// The entries are decoded on the fly by [`get_reserved_memory_regions`]
+    /// rather than reinterpreted as `&[FdtReserveEntry]`, so no particular
+    /// alignment of `data` is required.
+    fn _scan_mem_reserve(data: &[u8], off: usize) -> Result<&[u8], FdtError> {
+        let entry_size = mem::size_of::<FdtReserveEntry>();
+        let mut count = 0;
+        loop {
+            let start = off + count * entry_size;
+            let window = data.get(start..start + entry_size).ok_or(FdtError::Truncated)?;
+            let address = u64::from_be_bytes(window[0..8].try_into().unwrap());
+            let size = u64::from_be_bytes(window[8..16].try_into().unwrap());
+            if address == 0 && size == 0 {
+                break;
+            }
+            count += 1;
         }
+        data.get(off..off + count * entry_size).ok_or(FdtError::Truncated)
     }
 
     fn _parse_dt_struct(fdt_addr: *const u8, fdt_hdr: FdtHeader) -> (&'static [u8], FdtHeader) {
@@ -109,25 +391,23 @@ impl Fdt<'_> {
         }
     }
 
-    fn _parse_mem_reserve(fdt_addr: *const u8, fdt_hdr: FdtHeader) -> (&'static [FdtReserveEntry], FdtHeader) {
-        let mut mem_reserve;
+    fn _parse_mem_reserve(fdt_addr: *const u8, fdt_hdr: FdtHeader) -> (&'static [u8], FdtHeader) {
+        let entry_size = mem::size_of::<FdtReserveEntry>();
+        let block;
         unsafe {
-            mem_reserve = slice::from_raw_parts(fdt_addr
-                                                    .add(fdt_hdr.off_mem_rsvmap as usize) as *const FdtReserveEntry,
-                                                    ((fdt_hdr.off_dt_struct-fdt_hdr.off_mem_rsvmap)
-                                                    /mem::size_of::<FdtReserveEntry>() as u32) as usize);
-        }
-        let mem_iter = mem_reserve.iter();
-        for (len, entry) in  mem_iter.enumerate() {
-            if entry.address == 0 && entry.size == 0 { 
-                unsafe {
-                    mem_reserve = slice::from_raw_parts(fdt_addr
-                        .add(fdt_hdr.off_mem_rsvmap as usize) as *const FdtReserveEntry, len);
-                }
+            block = slice::from_raw_parts(fdt_addr.add(fdt_hdr.off_mem_rsvmap as usize),
+                                          (fdt_hdr.off_dt_struct - fdt_hdr.off_mem_rsvmap) as usize);
+        }
+        let mut count = 0;
+        for entry in block.chunks_exact(entry_size) {
+            let address = u64::from_be_bytes(entry[0..8].try_into().unwrap());
+            let size = u64::from_be_bytes(entry[8..16].try_into().unwrap());
+            if address == 0 && size == 0 {
                 break;
             }
+            count += 1;
         }
-        (mem_reserve,fdt_hdr)
+        (&block[..count * entry_size], fdt_hdr)
     }
 
     fn _parse_header(fdt_addr: *const u8) -> Result<FdtHeader, FdtError> {
@@ -159,33 +439,632 @@ impl Fdt<'_> {
     }
 }
 
-/*#[cfg(test)]
-mod tests {
-    use std::{fs::{self, File}, io::Read};
+fn align4(n: usize) -> usize {
+    (n + 3) & !3
+}
+
+/// A decoded property: its resolved name and its raw big-endian value bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct FdtProperty<'a> {
+    pub name: &'a str,
+    pub value: &'a [u8],
+}
+
+/// A cursor into the structure block pointing at a single node.
+///
+/// `FdtNode` is a lightweight handle: it borrows the [`Fdt`] and remembers the
+/// byte offset of the node's `FDT_BEGIN_NODE` token. Walking is done lazily and
+/// without allocation by re-decoding the token stream on demand.
+#[derive(Debug, Clone, Copy)]
+pub struct FdtNode<'a> {
+    fdt: &'a Fdt<'a>,
+    offset: usize,
+    /// Offset of the enclosing node's `FDT_BEGIN_NODE`, used to inherit the
+    /// `#address-cells`/`#size-cells` that govern this node's `reg`/`ranges`.
+    parent: Option<usize>,
+}
+
+/// Iterator over sibling nodes at a single nesting level.
+pub struct FdtSiblingIter<'a> {
+    fdt: &'a Fdt<'a>,
+    offset: usize,
+    parent: Option<usize>,
+}
+
+impl<'a> Iterator for FdtSiblingIter<'a> {
+    type Item = FdtNode<'a>;
+
+    fn next(&mut self) -> Option<FdtNode<'a>> {
+        loop {
+            match self.fdt.struct_token(self.offset)? {
+                FDT_NOP => self.offset += 4,
+                FDT_BEGIN_NODE => {
+                    let node = FdtNode { fdt: self.fdt, offset: self.offset, parent: self.parent };
+                    self.offset = self.fdt.skip_node(self.offset)?;
+                    return Some(node);
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Deepest nesting for which [`FdtAllNodesIter`] tracks the parent offset.
+/// Device trees are rarely more than a handful of levels deep; nodes below this
+/// are still yielded, but with no parent context.
+const ALL_NODES_MAX_DEPTH: usize = 32;
+
+/// Iterator over every node in the tree, in structure-block order.
+///
+/// Unlike [`FdtSiblingIter`] this descends the whole tree, so it maintains a
+/// stack of ancestor offsets to hand each yielded node its parent (and thus the
+/// `#address-cells`/`#size-cells` needed to decode `reg`/`ranges`).
+pub struct FdtAllNodesIter<'a> {
+    fdt: &'a Fdt<'a>,
+    offset: usize,
+    stack: [usize; ALL_NODES_MAX_DEPTH],
+    depth: usize,
+}
+
+impl<'a> Iterator for FdtAllNodesIter<'a> {
+    type Item = FdtNode<'a>;
+
+    fn next(&mut self) -> Option<FdtNode<'a>> {
+        loop {
+            match self.fdt.struct_token(self.offset)? {
+                FDT_BEGIN_NODE => {
+                    let parent = match self.depth {
+                        0 => None,
+                        d if d <= ALL_NODES_MAX_DEPTH => Some(self.stack[d - 1]),
+                        _ => None,
+                    };
+                    let node = FdtNode { fdt: self.fdt, offset: self.offset, parent };
+                    if self.depth < ALL_NODES_MAX_DEPTH {
+                        self.stack[self.depth] = self.offset;
+                    }
+                    self.depth += 1;
+                    let start = self.offset + 4;
+                    let bytes = self.fdt.dt_struct.get(start..)?;
+                    let nul = bytes.iter().position(|&b| b == 0).map(|p| p + 1)?;
+                    self.offset = align4(start + nul);
+                    return Some(node);
+                }
+                FDT_PROP => {
+                    let len = self.fdt.struct_token(self.offset + 4)? as usize;
+                    self.offset = align4(self.offset + 12 + len);
+                }
+                FDT_END_NODE => {
+                    self.depth = self.depth.saturating_sub(1);
+                    self.offset += 4;
+                }
+                FDT_NOP => self.offset += 4,
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Iterator over the properties directly attached to a node.
+pub struct FdtPropIter<'a> {
+    fdt: &'a Fdt<'a>,
+    offset: usize,
+}
+
+impl<'a> Iterator for FdtPropIter<'a> {
+    type Item = FdtProperty<'a>;
+
+    fn next(&mut self) -> Option<FdtProperty<'a>> {
+        loop {
+            match self.fdt.struct_token(self.offset)? {
+                FDT_NOP => self.offset += 4,
+                FDT_PROP => {
+                    let len = self.fdt.struct_token(self.offset + 4)? as usize;
+                    let nameoff = self.fdt.struct_token(self.offset + 8)? as usize;
+                    let val_start = self.offset + 12;
+                    let value = self.fdt.dt_struct.get(val_start..val_start + len)?;
+                    let name = self.fdt.get_string(nameoff)?;
+                    self.offset = align4(val_start + len);
+                    return Some(FdtProperty { name, value });
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+impl<'a> FdtNode<'a> {
+    /// The node name, e.g. `serial@7e201000` (empty for the root node).
+    pub fn name(&self) -> &'a str {
+        let start = self.offset + 4;
+        let bytes = self.fdt.dt_struct.get(start..).unwrap_or(&[]);
+        CStr::from_bytes_until_nul(bytes)
+            .ok()
+            .and_then(|c| c.to_str().ok())
+            .unwrap_or("")
+    }
+
+    /// Iterate over the properties of this node.
+    pub fn properties(&self) -> FdtPropIter<'a> {
+        FdtPropIter { fdt: self.fdt, offset: self.after_name() }
+    }
+
+    /// Iterate over the immediate child nodes of this node.
+    pub fn children(&self) -> FdtSiblingIter<'a> {
+        FdtSiblingIter { fdt: self.fdt, offset: self.children_start(), parent: Some(self.offset) }
+    }
+
+    /// Offset of the first token following the (padded) node name.
+    fn after_name(&self) -> usize {
+        let start = self.offset + 4;
+        let bytes = self.fdt.dt_struct.get(start..).unwrap_or(&[]);
+        let nul = bytes.iter().position(|&b| b == 0).map(|p| p + 1).unwrap_or(bytes.len());
+        align4(start + nul)
+    }
+
+    /// Offset of the first child node token, skipping this node's properties.
+    fn children_start(&self) -> usize {
+        let mut off = self.after_name();
+        loop {
+            match self.fdt.struct_token(off) {
+                Some(FDT_NOP) => off += 4,
+                Some(FDT_PROP) => {
+                    let len = self.fdt.struct_token(off + 4).unwrap_or(0) as usize;
+                    off = align4(off + 12 + len);
+                }
+                _ => break,
+            }
+        }
+        off
+    }
+}
+
+/// A property value that can be decoded from its raw big-endian bytes.
+///
+/// Base impls cover the cell-encoded integers, NUL-terminated strings, the
+/// `<string-list>` form and a raw byte passthrough. The `#[derive(FromFdtNode)]`
+/// companion macro uses this trait to populate one struct field per property.
+pub trait FromFdtProperty<'a>: Sized {
+    fn from_fdt_property(bytes: &'a [u8]) -> Result<Self, FdtError>;
+}
 
+impl<'a> FromFdtProperty<'a> for u32 {
+    fn from_fdt_property(bytes: &'a [u8]) -> Result<Self, FdtError> {
+        let b = bytes.get(..4).ok_or(FdtError::NotFound)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+impl<'a> FromFdtProperty<'a> for u64 {
+    fn from_fdt_property(bytes: &'a [u8]) -> Result<Self, FdtError> {
+        let b = bytes.get(..8).ok_or(FdtError::NotFound)?;
+        Ok(u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+    }
+}
+
+impl<'a> FromFdtProperty<'a> for &'a str {
+    fn from_fdt_property(bytes: &'a [u8]) -> Result<Self, FdtError> {
+        CStr::from_bytes_until_nul(bytes)
+            .ok()
+            .and_then(|c| c.to_str().ok())
+            .ok_or(FdtError::NotFound)
+    }
+}
+
+impl<'a> FromFdtProperty<'a> for &'a [u8] {
+    fn from_fdt_property(bytes: &'a [u8]) -> Result<Self, FdtError> {
+        Ok(bytes)
+    }
+}
+
+impl<'a> FromFdtProperty<'a> for StringList<'a> {
+    fn from_fdt_property(bytes: &'a [u8]) -> Result<Self, FdtError> {
+        Ok(StringList { bytes })
+    }
+}
+
+/// Iterator over a `<string-list>` property: concatenated NUL-terminated strings.
+#[derive(Debug, Clone, Copy)]
+pub struct StringList<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for StringList<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        let end = self.bytes.iter().position(|&b| b == 0).unwrap_or(self.bytes.len());
+        let s = core::str::from_utf8(&self.bytes[..end]).ok()?;
+        self.bytes = self.bytes.get(end + 1..).unwrap_or(&[]);
+        Some(s)
+    }
+}
+
+/// Read `cells` big-endian `u32` cells as a single integer and return the
+/// remaining bytes.
+///
+/// Values are accumulated into a `u64`, so a `cells` count greater than 2
+/// (a `#address-cells`/`#size-cells` of 3 or 4, valid but uncommon) keeps only
+/// the low 64 bits — the high cells are dropped. Callers expecting such wide
+/// addresses must decode the raw bytes themselves.
+fn read_cells(bytes: &[u8], cells: usize) -> (u64, &[u8]) {
+    let mut value = 0u64;
+    for i in 0..cells {
+        let w = u32::from_be_bytes([
+            bytes[i * 4],
+            bytes[i * 4 + 1],
+            bytes[i * 4 + 2],
+            bytes[i * 4 + 3],
+        ]);
+        value = (value << 32) | w as u64;
+    }
+    (value, &bytes[cells * 4..])
+}
+
+/// Iterator over a `reg` blob split into `(address, size)` tuples according to
+/// the enclosing node's `#address-cells`/`#size-cells`.
+#[derive(Debug, Clone, Copy)]
+pub struct Reg<'a> {
+    bytes: &'a [u8],
+    address_cells: usize,
+    size_cells: usize,
+}
+
+impl<'a> Iterator for Reg<'a> {
+    type Item = (u64, u64);
+
+    fn next(&mut self) -> Option<(u64, u64)> {
+        let need = (self.address_cells + self.size_cells) * 4;
+        if need == 0 || self.bytes.len() < need {
+            return None;
+        }
+        let (address, rest) = read_cells(self.bytes, self.address_cells);
+        let (size, rest) = read_cells(rest, self.size_cells);
+        self.bytes = rest;
+        Some((address, size))
+    }
+}
+
+/// Iterator over a `ranges` blob split into `(child_address, parent_address,
+/// size)` tuples. The child address uses this node's `#address-cells`, the
+/// parent address the enclosing node's `#address-cells`, and the size this
+/// node's `#size-cells`.
+#[derive(Debug, Clone, Copy)]
+pub struct Ranges<'a> {
+    bytes: &'a [u8],
+    child_address_cells: usize,
+    parent_address_cells: usize,
+    size_cells: usize,
+}
+
+impl<'a> Iterator for Ranges<'a> {
+    type Item = (u64, u64, u64);
+
+    fn next(&mut self) -> Option<(u64, u64, u64)> {
+        let need = (self.child_address_cells + self.parent_address_cells + self.size_cells) * 4;
+        if need == 0 || self.bytes.len() < need {
+            return None;
+        }
+        let (child, rest) = read_cells(self.bytes, self.child_address_cells);
+        let (parent, rest) = read_cells(rest, self.parent_address_cells);
+        let (size, rest) = read_cells(rest, self.size_cells);
+        self.bytes = rest;
+        Some((child, parent, size))
+    }
+}
+
+/// A type deserialized from a whole [`FdtNode`], one field per property.
+///
+/// Implemented by hand for exotic layouts, or generated by
+/// `#[derive(FromFdtNode)]` from the companion `fdt_derive` crate.
+pub trait FromFdtNode<'a>: Sized {
+    fn from_fdt_node(node: &FdtNode<'a>) -> Result<Self, FdtError>;
+}
+
+impl<'a> FdtNode<'a> {
+    /// Look up a property of this node by name.
+    pub fn property_by_name(&self, name: &str) -> Option<FdtProperty<'a>> {
+        self.properties().find(|p| p.name == name)
+    }
+
+    /// Decode a single property into any [`FromFdtProperty`] type.
+    pub fn decode_property<T: FromFdtProperty<'a>>(&self, name: &str) -> Result<T, FdtError> {
+        let prop = self.property_by_name(name).ok_or(FdtError::NotFound)?;
+        T::from_fdt_property(prop.value)
+    }
+
+    /// The enclosing node, if this node was reached through the tree (so that
+    /// its inherited cell counts can be consulted).
+    fn parent_node(&self) -> Option<FdtNode<'a>> {
+        self.parent.map(|offset| FdtNode { fdt: self.fdt, offset, parent: None })
+    }
+
+    /// This node's `#address-cells`, defaulting to 2 when absent (per the spec).
+    pub fn address_cells(&self) -> usize {
+        self.cell_count("#address-cells").unwrap_or(2) as usize
+    }
+
+    /// This node's `#size-cells`, defaulting to 1 when absent (per the spec).
+    pub fn size_cells(&self) -> usize {
+        self.cell_count("#size-cells").unwrap_or(1) as usize
+    }
+
+    /// Decode this node's `reg` property, inheriting the `#address-cells` and
+    /// `#size-cells` from the enclosing node (defaulting to 2 and 1 when the
+    /// node was not reached through the tree or the parent omits them).
+    pub fn reg(&self) -> Option<Reg<'a>> {
+        let parent = self.parent_node();
+        let address_cells = parent.map(|p| p.address_cells()).unwrap_or(2);
+        let size_cells = parent.map(|p| p.size_cells()).unwrap_or(1);
+        self.property_by_name("reg")
+            .map(|p| Reg { bytes: p.value, address_cells, size_cells })
+    }
+
+    /// Decode this node's `ranges` property, using this node's own
+    /// `#address-cells`/`#size-cells` for the child side and the enclosing
+    /// node's `#address-cells` for the parent side.
+    pub fn ranges(&self) -> Option<Ranges<'a>> {
+        let parent = self.parent_node();
+        let child_address_cells = self.address_cells();
+        let parent_address_cells = parent.map(|p| p.address_cells()).unwrap_or(2);
+        let size_cells = self.size_cells();
+        self.property_by_name("ranges").map(|p| Ranges {
+            bytes: p.value,
+            child_address_cells,
+            parent_address_cells,
+            size_cells,
+        })
+    }
+
+    /// Read a `#address-cells`/`#size-cells`-style `u32` property, if present.
+    pub fn cell_count(&self, name: &str) -> Option<u32> {
+        self.property_by_name(name)
+            .and_then(|p| u32::from_fdt_property(p.value).ok())
+    }
+
+    /// This node's `phandle` (or legacy `linux,phandle`) value, if it has one.
+    pub fn phandle(&self) -> Option<u32> {
+        self.property_by_name("phandle")
+            .or_else(|| self.property_by_name("linux,phandle"))
+            .and_then(|p| u32::from_fdt_property(p.value).ok())
+    }
+}
+
+/// Match a structure-block node name against one path component, accepting both
+/// the bare `name` and the `name@unit-address` forms.
+fn name_matches(full: &str, component: &str) -> bool {
+    full == component
+        || matches!(full.split_once('@'), Some((base, _)) if base == component)
+}
+
+impl<'a> Fdt<'a> {
+    /// Iterate over the top-level nodes of the structure block.
+    pub fn nodes(&'a self) -> FdtSiblingIter<'a> {
+        FdtSiblingIter { fdt: self, offset: 0, parent: None }
+    }
+
+    /// Iterate over every node in the tree in structure-block order.
+    pub fn all_nodes(&'a self) -> FdtAllNodesIter<'a> {
+        FdtAllNodesIter { fdt: self, offset: 0, stack: [0; ALL_NODES_MAX_DEPTH], depth: 0 }
+    }
+
+    /// Resolve an absolute path like `/soc/serial@7e201000` to a node.
+    ///
+    /// Each `/`-separated component is matched against child node names at the
+    /// matching depth, accepting both the bare `name` and `name@unit-address`
+    /// spellings. The empty path (`/`) resolves to the root node.
+    pub fn find_node(&'a self, path: &str) -> Option<FdtNode<'a>> {
+        let mut current = self.nodes().next()?;
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            current = current.children().find(|c| name_matches(c.name(), component))?;
+        }
+        Some(current)
+    }
+
+    /// Find the node carrying the given `phandle`.
+    pub fn node_by_phandle(&'a self, phandle: u32) -> Option<FdtNode<'a>> {
+        self.all_nodes().find(|n| n.phandle() == Some(phandle))
+    }
+
+    /// Follow a reference property (e.g. `interrupt-parent`, `clocks`) whose
+    /// first cell is a phandle to the node it points at.
+    pub fn resolve_phandle(&'a self, prop_bytes: &[u8]) -> Option<FdtNode<'a>> {
+        let phandle = u32::from_fdt_property(prop_bytes).ok()?;
+        self.node_by_phandle(phandle)
+    }
+
+    /// Iterate over every `(phandle, node)` pair in the tree. Callers that do
+    /// many lookups can collect this into their own `phandle -> node` index
+    /// instead of rescanning per [`node_by_phandle`](Fdt::node_by_phandle) call.
+    pub fn phandles(&'a self) -> impl Iterator<Item = (u32, FdtNode<'a>)> + 'a {
+        self.all_nodes().filter_map(|n| n.phandle().map(|p| (p, n)))
+    }
+
+    /// Read a big-endian `u32` token at `off` in the structure block, bounds-checked.
+    fn struct_token(&self, off: usize) -> Option<u32> {
+        let b = self.dt_struct.get(off..off + 4)?;
+        Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Advance past the node beginning at `off`, returning the offset just after
+    /// its matching `FDT_END_NODE`. Every offset is bounds-checked against the
+    /// structure block, so a truncated or malformed stream yields `None` rather
+    /// than reading out of bounds.
+    fn skip_node(&self, off: usize) -> Option<usize> {
+        let mut off = off;
+        let mut depth = 0usize;
+        loop {
+            match self.struct_token(off)? {
+                FDT_BEGIN_NODE => {
+                    depth += 1;
+                    let start = off + 4;
+                    let bytes = self.dt_struct.get(start..)?;
+                    let nul = bytes.iter().position(|&b| b == 0).map(|p| p + 1)?;
+                    off = align4(start + nul);
+                }
+                FDT_PROP => {
+                    let len = self.struct_token(off + 4)? as usize;
+                    off = align4(off + 12 + len);
+                }
+                FDT_NOP => off += 4,
+                FDT_END_NODE => {
+                    off += 4;
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(off);
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
 
+    /// Build a small but representative tree into `buf` and return its length.
+    fn build_sample(buf: &mut [u8]) -> usize {
+        let mut w = FdtWriter::new(buf);
+        w.begin_node("").unwrap();
+        w.property_u32("#address-cells", 2).unwrap();
+        w.property_u32("#size-cells", 1).unwrap();
+
+        w.begin_node("memory@0").unwrap();
+        w.property_string("device_type", "memory").unwrap();
+        w.property_array_u32("reg", &[0x0, 0x4000_0000, 0x2000_0000]).unwrap();
+        w.end_node().unwrap();
+
+        w.begin_node("soc").unwrap();
+        w.property_u32("#address-cells", 1).unwrap();
+        w.property_u32("#size-cells", 1).unwrap();
+        w.begin_node("serial@7e201000").unwrap();
+        w.property_string("compatible", "arm,pl011").unwrap();
+        w.property_array_u32("reg", &[0x7e20_1000, 0x1000]).unwrap();
+        w.property_u32("phandle", 1).unwrap();
+        w.end_node().unwrap();
+        w.end_node().unwrap();
+
+        w.end_node().unwrap();
+        w.finish().unwrap()
+    }
+
     #[test]
-    fn it_works() {
-        let filename = "/home/arryn/fdt/t8103-j313.dtb";
-        let mut f = File::open(filename).unwrap();
-        let metadata = fs::metadata(&filename).expect("unable to read metadata");
-        let mut buffer = vec![0; metadata.len() as usize];
-        f.read(&mut buffer).expect("buffer overflow");
-        let memory_regions = [
-            FdtReserveEntry {address: 0x100_u64.to_be(), size: 0x600_u64.to_be()},
-            FdtReserveEntry {address: 0x200_u64.to_be(), size: 0x700_u64.to_be()},
-            FdtReserveEntry {address: 0x300_u64.to_be(), size: 0x800_u64.to_be()},
-            FdtReserveEntry {address: 0x400_u64.to_be(), size: 0x900_u64.to_be()},
-            FdtReserveEntry {address: 0x500_u64.to_be(), size: 0xA00_u64.to_be()},
-            FdtReserveEntry {address: 0x000_u64.to_be(), size: 0x000_u64.to_be()},
-            ];
-
-        let mut fdt = Fdt::new(buffer.as_ptr()).unwrap();
-        fdt.reserved_memory = &memory_regions;
-        for x in fdt.get_reserved_memory_regions() {
-            let m = x;
-            println!("{m:#x?}");
-        }
-    }
-}*/
+    fn writer_from_bytes_roundtrip() {
+        let mut buf = [0u8; 512];
+        let len = build_sample(&mut buf);
+        let fdt = Fdt::from_bytes(&buf[..len]).unwrap();
+
+        let root = fdt.nodes().next().unwrap();
+        assert_eq!(root.name(), "");
+        assert!(fdt.get_reserved_memory_regions().next().is_none());
+
+        let mem = fdt.find_node("/memory@0").unwrap();
+        assert_eq!(mem.decode_property::<&str>("device_type").unwrap(), "memory");
+        let mut reg = mem.reg().unwrap();
+        assert_eq!(reg.next(), Some((0x4000_0000, 0x2000_0000)));
+        assert_eq!(reg.next(), None);
+    }
+
+    #[test]
+    fn node_and_property_traversal() {
+        let mut buf = [0u8; 512];
+        let len = build_sample(&mut buf);
+        let fdt = Fdt::from_bytes(&buf[..len]).unwrap();
+
+        let root = fdt.nodes().next().unwrap();
+        let mut children = root.children();
+        assert_eq!(children.next().unwrap().name(), "memory@0");
+        assert_eq!(children.next().unwrap().name(), "soc");
+        assert!(children.next().is_none());
+
+        let soc = fdt.find_node("/soc").unwrap();
+        assert_eq!(soc.children().next().unwrap().name(), "serial@7e201000");
+
+        let mem = fdt.find_node("/memory@0").unwrap();
+        let dt = mem.properties().find(|p| p.name == "device_type").unwrap();
+        assert_eq!(dt.value, b"memory\0");
+    }
+
+    #[test]
+    fn path_and_phandle_lookup() {
+        let mut buf = [0u8; 512];
+        let len = build_sample(&mut buf);
+        let fdt = Fdt::from_bytes(&buf[..len]).unwrap();
+
+        assert_eq!(fdt.find_node("/").unwrap().name(), "");
+        assert_eq!(fdt.find_node("/soc/serial@7e201000").unwrap().name(), "serial@7e201000");
+        // The bare node name resolves the `name@unit-address` form.
+        assert_eq!(fdt.find_node("/soc/serial").unwrap().name(), "serial@7e201000");
+        assert!(fdt.find_node("/soc/missing").is_none());
+
+        assert_eq!(fdt.node_by_phandle(1).unwrap().name(), "serial@7e201000");
+        assert!(fdt.node_by_phandle(99).is_none());
+        let resolved = fdt.resolve_phandle(&1u32.to_be_bytes()).unwrap();
+        assert_eq!(resolved.name(), "serial@7e201000");
+
+        // A phandle-resolved node must still inherit its parent's 1/1 cells.
+        let mut reg = resolved.reg().unwrap();
+        assert_eq!(reg.next(), Some((0x7e20_1000, 0x1000)));
+    }
+
+    #[derive(FromFdtNode)]
+    struct Serial<'a> {
+        compatible: &'a str,
+        #[fdt(reg)]
+        reg: Reg<'a>,
+        phandle: u32,
+        #[fdt(rename = "status")]
+        status: Option<&'a str>,
+    }
+
+    #[derive(FromFdtNode)]
+    struct Cells {
+        #[fdt(rename = "#address-cells")]
+        address_cells: u32,
+        #[fdt(rename = "#size-cells")]
+        size_cells: u32,
+    }
+
+    #[test]
+    fn derive_decode_owned_struct() {
+        let mut buf = [0u8; 512];
+        let len = build_sample(&mut buf);
+        let fdt = Fdt::from_bytes(&buf[..len]).unwrap();
+
+        let root = fdt.find_node("/").unwrap();
+        let cells = Cells::from_fdt_node(&root).unwrap();
+        assert_eq!(cells.address_cells, 2);
+        assert_eq!(cells.size_cells, 1);
+    }
+
+    #[test]
+    fn derive_decode_node() {
+        let mut buf = [0u8; 512];
+        let len = build_sample(&mut buf);
+        let fdt = Fdt::from_bytes(&buf[..len]).unwrap();
+
+        let node = fdt.find_node("/soc/serial@7e201000").unwrap();
+        let serial = Serial::from_fdt_node(&node).unwrap();
+        assert_eq!(serial.compatible, "arm,pl011");
+        assert_eq!(serial.phandle, 1);
+        assert_eq!(serial.status, None);
+
+        // `reg` inherits the soc node's 1/1 cells, not the root's 2/1.
+        let mut reg = serial.reg;
+        assert_eq!(reg.next(), Some((0x7e20_1000, 0x1000)));
+        assert_eq!(reg.next(), None);
+    }
+
+    #[test]
+    fn from_bytes_rejects_bad_input() {
+        assert!(matches!(Fdt::from_bytes(&[0u8; 8]), Err(FdtError::Truncated)));
+        let mut buf = [0u8; 512];
+        let len = build_sample(&mut buf);
+        buf[0] = 0;
+        assert!(matches!(Fdt::from_bytes(&buf[..len]), Err(FdtError::InvalidMagic)));
+    }
+}